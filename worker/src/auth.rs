@@ -0,0 +1,112 @@
+use base64::Engine;
+use worker::*;
+
+use crate::signing::{constant_time_eq, SignatureCheck};
+
+/// Which routes require credentials, configured via the `AUTH_SCOPE` env var.
+enum AuthScope {
+    /// No route requires credentials.
+    None,
+    /// Only routes marked as writes require credentials: the mutating routes (PUT,
+    /// DELETE, multipart) and `/sign`.
+    WritesOnly,
+    /// Every gated route requires credentials, reads included.
+    All,
+}
+
+impl AuthScope {
+    fn from_env(ctx: &RouteContext<()>) -> Self {
+        match ctx.var("AUTH_SCOPE").ok().map(|v| v.to_string()) {
+            Some(ref s) if s == "all" => Self::All,
+            Some(ref s) if s == "writes-only" => Self::WritesOnly,
+            _ => Self::None,
+        }
+    }
+}
+
+fn unauthorized() -> Result<Response> {
+    let mut headers = Headers::new();
+    headers.set("www-authenticate", "Basic realm=\"r2-browser\"")?;
+    Ok(Response::error("Unauthorized", 401)?.with_headers(headers))
+}
+
+fn check_basic(header: &str, ctx: &RouteContext<()>) -> Result<bool> {
+    let Some(encoded) = header.strip_prefix("Basic ") else {
+        return Ok(false);
+    };
+    let Ok(decoded) = base64::engine::general_purpose::STANDARD.decode(encoded) else {
+        return Ok(false);
+    };
+    let Ok(decoded) = String::from_utf8(decoded) else {
+        return Ok(false);
+    };
+    let Some((user, password)) = decoded.split_once(':') else {
+        return Ok(false);
+    };
+
+    // A scheme the operator never configured just doesn't match — not a server error.
+    let Ok(expected_user) = ctx.secret("BASIC_AUTH_USER") else {
+        return Ok(false);
+    };
+    let Ok(expected_password) = ctx.secret("BASIC_AUTH_PASSWORD") else {
+        return Ok(false);
+    };
+
+    Ok(
+        constant_time_eq(user.as_bytes(), expected_user.to_string().as_bytes())
+            && constant_time_eq(password.as_bytes(), expected_password.to_string().as_bytes()),
+    )
+}
+
+fn check_bearer(header: &str, ctx: &RouteContext<()>) -> Result<bool> {
+    let Some(token) = header.strip_prefix("Bearer ") else {
+        return Ok(false);
+    };
+    let Ok(expected) = ctx.secret("BEARER_TOKEN") else {
+        return Ok(false);
+    };
+    Ok(constant_time_eq(token.as_bytes(), expected.to_string().as_bytes()))
+}
+
+/// Gates a route based on `AUTH_SCOPE`. `is_write` marks routes that mutate the
+/// bucket (PUT, DELETE, multipart completion); returns `Some(401)` to short-circuit
+/// the caller, or `None` when the request may proceed.
+pub fn authorize(req: &Request, ctx: &RouteContext<()>, is_write: bool) -> Result<Option<Response>> {
+    let requires_auth = match AuthScope::from_env(ctx) {
+        AuthScope::None => false,
+        AuthScope::WritesOnly => is_write,
+        AuthScope::All => true,
+    };
+    if !requires_auth {
+        return Ok(None);
+    }
+
+    let Some(header) = req.headers().get("authorization")? else {
+        return Ok(Some(unauthorized()?));
+    };
+
+    let authorized = check_basic(&header, ctx)?
+        || check_bearer(&header, ctx)?;
+
+    if authorized {
+        Ok(None)
+    } else {
+        Ok(Some(unauthorized()?))
+    }
+}
+
+/// Like `authorize`, but for `/objects/:key` routes: a validly signed request (chunk0-6)
+/// is meant to work without credentials at all, so it must bypass this gate entirely,
+/// and an invalid/expired signature must fall through to its own 403/410 rather than
+/// being masked by a 401 here. Only requests carrying no `sig` fall back to `authorize`.
+pub async fn authorize_object(
+    req: &Request,
+    ctx: &RouteContext<()>,
+    method: &str,
+    is_write: bool,
+) -> Result<Option<Response>> {
+    match crate::objects::signature_check(req, ctx, method).await? {
+        SignatureCheck::NotPresent => authorize(req, ctx, is_write),
+        SignatureCheck::Valid | SignatureCheck::Invalid | SignatureCheck::Expired => Ok(None),
+    }
+}
@@ -0,0 +1,140 @@
+use std::io::{Read, Write};
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use worker::*;
+
+pub const METADATA_ALGORITHM: &str = "x-compression";
+pub const METADATA_ORIGINAL_LENGTH: &str = "x-original-length";
+
+/// Algorithm used to compress an object's body before it's written to R2.
+/// Persisted in the object's custom metadata so `GET` knows how to reverse it.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum CompressionAlgorithm {
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl CompressionAlgorithm {
+    /// Picks the algorithm for a `PUT`: an explicit `?compress=` wins, otherwise falls
+    /// back to the deployment's configured default (`COMPRESSION_DEFAULT` env var),
+    /// the same pattern `AuthScope::from_env` uses for `AUTH_SCOPE`.
+    pub fn from_query_param(value: Option<&str>, ctx: &RouteContext<()>) -> Self {
+        match value {
+            Some("gzip") => Self::Gzip,
+            Some("zstd") => Self::Zstd,
+            Some("none") => Self::None,
+            _ => Self::from_configured_default(ctx),
+        }
+    }
+
+    fn from_configured_default(ctx: &RouteContext<()>) -> Self {
+        match ctx.var("COMPRESSION_DEFAULT").ok().map(|v| v.to_string()) {
+            Some(ref s) if s == "gzip" => Self::Gzip,
+            Some(ref s) if s == "zstd" => Self::Zstd,
+            _ => Self::default(),
+        }
+    }
+
+    pub fn from_metadata_value(value: Option<&str>) -> Self {
+        match value {
+            Some("gzip") => Self::Gzip,
+            Some("zstd") => Self::Zstd,
+            _ => Self::None,
+        }
+    }
+
+    pub fn metadata_value(&self) -> Option<&'static str> {
+        match self {
+            Self::None => None,
+            Self::Gzip => Some("gzip"),
+            Self::Zstd => Some("zstd"),
+        }
+    }
+
+    /// The `Content-Encoding` token to send when passing the compressed bytes
+    /// straight through to a client that advertised support for it.
+    pub fn content_encoding(&self) -> Option<&'static str> {
+        self.metadata_value()
+    }
+
+    pub fn accepted_by(&self, accept_encoding: Option<&str>) -> bool {
+        match (self, accept_encoding) {
+            (Self::None, _) => true,
+            (_, None) => false,
+            (_, Some(header)) => {
+                let token = self.content_encoding().unwrap_or_default();
+                header
+                    .split(',')
+                    .any(|candidate| candidate.trim() == token)
+            }
+        }
+    }
+
+    pub fn compress(&self, data: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            Self::None => Ok(data.to_vec()),
+            Self::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+                encoder
+                    .write_all(data)
+                    .map_err(|e| Error::RustError(format!("gzip compression failed: {e}")))?;
+                encoder
+                    .finish()
+                    .map_err(|e| Error::RustError(format!("gzip compression failed: {e}")))
+            }
+            Self::Zstd => zstd::stream::encode_all(data, 0)
+                .map_err(|e| Error::RustError(format!("zstd compression failed: {e}"))),
+        }
+    }
+
+    /// Decompresses `data`, stopping once `limit` decompressed bytes have been produced
+    /// (or the whole stream, when `limit` is `None`).
+    /// Neither gzip nor zstd support seeking within a compressed stream, so serving a
+    /// range still means decompressing from the start — but bounding the output here
+    /// keeps the decompressed buffer no larger than the end of the requested range
+    /// instead of the whole object, which matters for a range read near the start of a
+    /// large compressed file. `data` itself is the whole compressed input.
+    pub fn decompress_upto(&self, data: &[u8], limit: Option<u64>) -> Result<Vec<u8>> {
+        match self {
+            Self::None => Ok(match limit {
+                Some(limit) => data[..data.len().min(limit as usize)].to_vec(),
+                None => data.to_vec(),
+            }),
+            Self::Gzip => {
+                let decoder = GzDecoder::new(data);
+                read_bounded(decoder, limit)
+                    .map_err(|e| Error::RustError(format!("gzip decompression failed: {e}")))
+            }
+            Self::Zstd => {
+                let decoder = zstd::stream::read::Decoder::new(data)
+                    .map_err(|e| Error::RustError(format!("zstd decompression failed: {e}")))?;
+                read_bounded(decoder, limit)
+                    .map_err(|e| Error::RustError(format!("zstd decompression failed: {e}")))
+            }
+        }
+    }
+}
+
+fn read_bounded(reader: impl Read, limit: Option<u64>) -> std::io::Result<Vec<u8>> {
+    let mut out = Vec::new();
+    match limit {
+        Some(limit) => {
+            reader.take(limit).read_to_end(&mut out)?;
+        }
+        None => {
+            reader.take(u64::MAX).read_to_end(&mut out)?;
+        }
+    }
+    Ok(out)
+}
+
+impl Default for CompressionAlgorithm {
+    /// Used when `COMPRESSION_DEFAULT` isn't set either — compresses nothing, preserving
+    /// today's behavior for deployments that never configure a default.
+    fn default() -> Self {
+        Self::None
+    }
+}
@@ -1,5 +1,11 @@
 use worker::*;
 
+mod auth;
+mod compression;
+mod metrics;
+mod objects;
+mod signing;
+mod ui;
 mod utils;
 
 fn log_request(req: &Request) {
@@ -42,6 +48,93 @@ pub async fn main(req: Request, env: Env, _ctx: worker::Context) -> Result<Respo
                 "version": env!("CARGO_PKG_VERSION")
             }))
         })
+        .get_async("/list", |req, ctx| async move {
+            if let Some(denied) = auth::authorize(&req, &ctx, false)? {
+                return Ok(denied);
+            }
+            metrics::timed("/list", req, ctx, objects::list_objects).await
+        })
+        .get_async("/objects/:key", |req, ctx| async move {
+            if let Some(denied) = auth::authorize_object(&req, &ctx, "GET", false).await? {
+                return Ok(denied);
+            }
+            metrics::timed("/objects/:key", req, ctx, objects::get_object).await
+        })
+        .put_async("/objects/:key", |req, ctx| async move {
+            if let Some(denied) = auth::authorize_object(&req, &ctx, "PUT", true).await? {
+                return Ok(denied);
+            }
+            metrics::timed("/objects/:key", req, ctx, objects::put_object).await
+        })
+        .delete_async("/objects/:key", |req, ctx| async move {
+            if let Some(denied) = auth::authorize_object(&req, &ctx, "DELETE", true).await? {
+                return Ok(denied);
+            }
+            metrics::timed("/objects/:key", req, ctx, objects::delete_object).await
+        })
+        .post_async("/objects/:key/multipart", |req, ctx| async move {
+            if let Some(denied) = auth::authorize(&req, &ctx, true)? {
+                return Ok(denied);
+            }
+            metrics::timed(
+                "/objects/:key/multipart",
+                req,
+                ctx,
+                objects::create_multipart_upload,
+            )
+            .await
+        })
+        .put_async(
+            "/objects/:key/multipart/:uploadId/:partNumber",
+            |req, ctx| async move {
+                if let Some(denied) = auth::authorize(&req, &ctx, true)? {
+                    return Ok(denied);
+                }
+                metrics::timed(
+                    "/objects/:key/multipart/:uploadId/:partNumber",
+                    req,
+                    ctx,
+                    objects::upload_part,
+                )
+                .await
+            },
+        )
+        .post_async(
+            "/objects/:key/multipart/:uploadId/complete",
+            |req, ctx| async move {
+                if let Some(denied) = auth::authorize(&req, &ctx, true)? {
+                    return Ok(denied);
+                }
+                metrics::timed(
+                    "/objects/:key/multipart/:uploadId/complete",
+                    req,
+                    ctx,
+                    objects::complete_multipart_upload,
+                )
+                .await
+            },
+        )
+        .get_async("/ui/*path", |req, ctx| async move {
+            if let Some(denied) = auth::authorize(&req, &ctx, false)? {
+                return Ok(denied);
+            }
+            metrics::timed("/ui/*path", req, ctx, ui::serve_ui).await
+        })
+        .get_async("/metrics", |req, ctx| async move {
+            if let Some(denied) = auth::authorize(&req, &ctx, false)? {
+                return Ok(denied);
+            }
+            metrics::metrics_handler(req, ctx).await
+        })
+        .post_async("/sign", |req, ctx| async move {
+            // Minting a signed URL hands out access to an object, so it's gated like a
+            // write: anyone who can self-issue a valid link could otherwise skip the
+            // credential check entirely.
+            if let Some(denied) = auth::authorize(&req, &ctx, true)? {
+                return Ok(denied);
+            }
+            metrics::timed("/sign", req, ctx, signing::sign_handler).await
+        })
         .run(req, env)
         .await
 }
\ No newline at end of file
@@ -0,0 +1,125 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+use worker::*;
+
+/// Upper bound (inclusive) of each latency bucket, in milliseconds. Workers are
+/// short-lived so a handful of power-of-two buckets is plenty of resolution.
+const BUCKET_BOUNDS_MS: &[f64] = &[1.0, 2.0, 4.0, 8.0, 16.0, 32.0, 64.0, 128.0, 256.0, 512.0, 1024.0, 2048.0, 4096.0];
+
+#[derive(Default)]
+struct Histogram {
+    bucket_counts: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    /// `bucket_counts[i]` already holds the cumulative "`<= bound`" count (Prometheus's
+    /// `le` semantics), including the trailing `+Inf` bucket — callers must render it
+    /// directly rather than re-accumulating.
+    fn observe(&mut self, value_ms: f64) {
+        if self.bucket_counts.is_empty() {
+            self.bucket_counts = vec![0; BUCKET_BOUNDS_MS.len() + 1];
+        }
+        for (i, bound) in BUCKET_BOUNDS_MS.iter().enumerate() {
+            if value_ms <= *bound {
+                self.bucket_counts[i] += 1;
+            }
+        }
+        *self.bucket_counts.last_mut().unwrap() += 1;
+        self.sum += value_ms;
+        self.count += 1;
+    }
+}
+
+#[derive(Default)]
+struct Registry {
+    route_visits: HashMap<String, u64>,
+    route_latency: HashMap<String, Histogram>,
+    r2_outcomes: HashMap<(&'static str, &'static str), u64>,
+}
+
+static REGISTRY: Lazy<Mutex<Registry>> = Lazy::new(|| Mutex::new(Registry::default()));
+
+fn record_route(route: &str, elapsed_ms: f64) {
+    let mut registry = REGISTRY.lock().unwrap();
+    *registry.route_visits.entry(route.to_string()).or_insert(0) += 1;
+    registry
+        .route_latency
+        .entry(route.to_string())
+        .or_default()
+        .observe(elapsed_ms);
+}
+
+/// Records the outcome of an R2 operation (`get`/`put`/`delete`/...) for the
+/// `r2_operations_total` counter rendered at `/metrics`.
+pub fn record_r2_outcome(operation: &'static str, success: bool) {
+    let outcome = if success { "success" } else { "error" };
+    let mut registry = REGISTRY.lock().unwrap();
+    *registry
+        .r2_outcomes
+        .entry((operation, outcome))
+        .or_insert(0) += 1;
+}
+
+/// Wraps a route handler so its visit count and latency are recorded under `route`
+/// before the response is returned.
+pub async fn timed<F, Fut>(route: &'static str, req: Request, ctx: RouteContext<()>, handler: F) -> Result<Response>
+where
+    F: FnOnce(Request, RouteContext<()>) -> Fut,
+    Fut: Future<Output = Result<Response>>,
+{
+    let start = Date::now().as_millis() as f64;
+    let result = handler(req, ctx).await;
+    record_route(route, Date::now().as_millis() as f64 - start);
+    result
+}
+
+pub async fn metrics_handler(_req: Request, _ctx: RouteContext<()>) -> Result<Response> {
+    let registry = REGISTRY.lock().unwrap();
+    let mut out = String::new();
+
+    out.push_str("# HELP routes_visited_total Number of requests served per route.\n");
+    out.push_str("# TYPE routes_visited_total counter\n");
+    for (route, count) in &registry.route_visits {
+        out.push_str(&format!("routes_visited_total{{route=\"{route}\"}} {count}\n"));
+    }
+
+    out.push_str("# HELP response_time_ms Response latency per route, in milliseconds.\n");
+    out.push_str("# TYPE response_time_ms histogram\n");
+    for (route, histogram) in &registry.route_latency {
+        for (i, bound) in BUCKET_BOUNDS_MS.iter().enumerate() {
+            let count = histogram.bucket_counts.get(i).copied().unwrap_or(0);
+            out.push_str(&format!(
+                "response_time_ms_bucket{{route=\"{route}\",le=\"{bound}\"}} {count}\n"
+            ));
+        }
+        out.push_str(&format!(
+            "response_time_ms_bucket{{route=\"{route}\",le=\"+Inf\"}} {}\n",
+            histogram.bucket_counts.last().copied().unwrap_or(0)
+        ));
+        out.push_str(&format!(
+            "response_time_ms_sum{{route=\"{route}\"}} {}\n",
+            histogram.sum
+        ));
+        out.push_str(&format!(
+            "response_time_ms_count{{route=\"{route}\"}} {}\n",
+            histogram.count
+        ));
+    }
+
+    out.push_str("# HELP r2_operations_total R2 operations by outcome.\n");
+    out.push_str("# TYPE r2_operations_total counter\n");
+    for ((operation, outcome), count) in &registry.r2_outcomes {
+        out.push_str(&format!(
+            "r2_operations_total{{operation=\"{operation}\",outcome=\"{outcome}\"}} {count}\n"
+        ));
+    }
+
+    let mut headers = Headers::new();
+    headers.set("content-type", "text/plain; version=0.0.4")?;
+    Ok(Response::ok(out)?.with_headers(headers))
+}
@@ -0,0 +1,518 @@
+use serde::{Deserialize, Serialize};
+use worker::*;
+
+use crate::compression::{CompressionAlgorithm, METADATA_ALGORITHM, METADATA_ORIGINAL_LENGTH};
+use crate::signing::{self, SignatureCheck};
+
+/// A single page of a bucket listing, shaped for cursor-based pagination.
+#[derive(Serialize)]
+struct ListPage {
+    objects: Vec<ObjectSummary>,
+    truncated: bool,
+    cursor: Option<String>,
+}
+
+#[derive(Serialize)]
+struct ObjectSummary {
+    key: String,
+    size: u64,
+    etag: String,
+    uploaded: String,
+}
+
+#[derive(Deserialize)]
+struct CompletedPart {
+    #[serde(rename = "partNumber")]
+    part_number: u16,
+    etag: String,
+}
+
+fn key_param(ctx: &RouteContext<()>) -> Result<String> {
+    ctx.param("key")
+        .map(|k| k.to_string())
+        .ok_or_else(|| Error::RustError("missing key param".into()))
+}
+
+/// Checks `req`'s `exp`/`sig` query params, if present, against `SIGNING_SECRET`.
+/// Exposed so the router can bypass `auth::authorize` for a validly signed request —
+/// a presigned link is meant to work without credentials — and can let an
+/// invalid/expired one fall straight through to its 403/410 rather than being
+/// masked by a 401 first.
+pub(crate) async fn signature_check(req: &Request, ctx: &RouteContext<()>, method: &str) -> Result<SignatureCheck> {
+    let key = key_param(ctx)?;
+    let secret = match ctx.secret("SIGNING_SECRET") {
+        Ok(secret) => secret.to_string(),
+        Err(_) => return Ok(SignatureCheck::NotPresent),
+    };
+    let now_secs = Date::now().as_millis() / 1000;
+
+    signing::verify(req, &key, method, &secret, now_secs)
+}
+
+/// Returns `Some(response)` to short-circuit the caller with 403/410, or `None`
+/// to proceed with the request as normal (including requests with no signature).
+async fn check_signature(req: &Request, ctx: &RouteContext<()>, method: &str) -> Result<Option<Response>> {
+    match signature_check(req, ctx, method).await? {
+        SignatureCheck::NotPresent | SignatureCheck::Valid => Ok(None),
+        SignatureCheck::Invalid => Ok(Some(Response::error("Forbidden", 403)?)),
+        SignatureCheck::Expired => Ok(Some(Response::error("Gone", 410)?)),
+    }
+}
+
+/// An inclusive byte range resolved against an object's total size.
+#[derive(Debug, PartialEq, Eq)]
+struct ByteRange {
+    start: u64,
+    end: u64,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum RangeRequest {
+    /// No `Range` header, or one we don't support (multi-range) — serve the full body.
+    Full,
+    Satisfiable(ByteRange),
+    Unsatisfiable,
+}
+
+/// Parses a single `Range: bytes=start-end` header. Multi-range requests (containing a
+/// comma) fall back to `Full` so callers serve a plain 200 body, per the request's note
+/// that multi-range isn't worth supporting yet.
+fn parse_range(header: &str, total: u64) -> RangeRequest {
+    let Some(spec) = header.strip_prefix("bytes=") else {
+        return RangeRequest::Full;
+    };
+    if spec.contains(',') || total == 0 {
+        return RangeRequest::Full;
+    }
+
+    let (start, end) = match spec.split_once('-') {
+        Some(("", suffix)) => {
+            let Ok(suffix) = suffix.parse::<u64>() else {
+                return RangeRequest::Full;
+            };
+            let start = total.saturating_sub(suffix);
+            (start, total - 1)
+        }
+        Some((start, "")) => {
+            let Ok(start) = start.parse::<u64>() else {
+                return RangeRequest::Full;
+            };
+            (start, total - 1)
+        }
+        Some((start, end)) => {
+            let (Ok(start), Ok(end)) = (start.parse::<u64>(), end.parse::<u64>()) else {
+                return RangeRequest::Full;
+            };
+            (start, end.min(total - 1))
+        }
+        None => return RangeRequest::Full,
+    };
+
+    if start >= total || start > end {
+        RangeRequest::Unsatisfiable
+    } else {
+        RangeRequest::Satisfiable(ByteRange { start, end })
+    }
+}
+
+pub async fn get_object(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    let result = get_object_inner(req, ctx).await;
+    crate::metrics::record_r2_outcome("get", result.is_ok());
+    result
+}
+
+async fn get_object_inner(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    let bucket = ctx.bucket("BUCKET")?;
+    let key = key_param(&ctx)?;
+
+    if let Some(denied) = check_signature(&req, &ctx, "GET").await? {
+        return Ok(denied);
+    }
+
+    let head = match bucket.head(&key).await? {
+        Some(head) => head,
+        None => return Response::error("Not Found", 404),
+    };
+    let custom_metadata = head.custom_metadata()?;
+    let algorithm =
+        CompressionAlgorithm::from_metadata_value(custom_metadata.get(METADATA_ALGORITHM).map(String::as_str));
+    let original_length = custom_metadata
+        .get(METADATA_ORIGINAL_LENGTH)
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or_else(|| head.size());
+
+    // A client that can decode the stored encoding gets the compressed bytes untouched;
+    // range semantics then apply to the compressed stream, same as an uncompressed object.
+    let accept_encoding = req.headers().get("accept-encoding")?;
+    if algorithm != CompressionAlgorithm::None && algorithm.accepted_by(accept_encoding.as_deref())
+    {
+        return stream_passthrough(&bucket, &key, &req, head.size(), algorithm).await;
+    }
+
+    if algorithm == CompressionAlgorithm::None {
+        return stream_passthrough(&bucket, &key, &req, head.size(), algorithm).await;
+    }
+
+    let range = req
+        .headers()
+        .get("range")?
+        .map(|header| parse_range(&header, original_length))
+        .unwrap_or(RangeRequest::Full);
+
+    if let RangeRequest::Unsatisfiable = range {
+        let mut headers = Headers::new();
+        headers.set("content-range", &format!("bytes */{original_length}"))?;
+        headers.set("accept-ranges", "bytes")?;
+        return Ok(Response::error("Range Not Satisfiable", 416)?.with_headers(headers));
+    }
+
+    // Neither gzip nor zstd support seeking within a compressed stream, so honoring a
+    // range still means decompressing from the start — but we stop as soon as we've
+    // produced enough bytes to answer the range, rather than buffering the whole object.
+    //
+    // Known limitation: we still fetch the entire compressed object via `body.bytes()`
+    // before decompressing, rather than decompressing incrementally off `body.stream()`.
+    // `flate2`/`zstd`'s decoders need a synchronous `Read`, and bridging that against
+    // R2's async `ReadableStream` isn't wired up here, so fetching is O(compressed size)
+    // even though the decompression work itself is now bounded by the range.
+    let object = match bucket.get(&key).execute().await? {
+        Some(object) => object,
+        None => return Response::error("Not Found", 404),
+    };
+    let compressed = match object.body() {
+        Some(body) => body.bytes().await?,
+        None => return Response::error("Not Found", 404),
+    };
+    let decompress_limit = match &range {
+        RangeRequest::Satisfiable(ByteRange { end, .. }) => Some(end + 1),
+        _ => None,
+    };
+    let decompressed = algorithm.decompress_upto(&compressed, decompress_limit)?;
+
+    let mut headers = Headers::new();
+    headers.set("etag", &object.etag())?;
+    headers.set("accept-ranges", "bytes")?;
+    if let Some(content_type) = object.http_metadata().content_type {
+        headers.set("content-type", &content_type)?;
+    }
+
+    let (slice, status) = if let RangeRequest::Satisfiable(ByteRange { start, end }) = &range {
+        headers.set(
+            "content-length",
+            &(end - start + 1).to_string(),
+        )?;
+        headers.set(
+            "content-range",
+            &format!("bytes {start}-{end}/{original_length}"),
+        )?;
+        (&decompressed[*start as usize..=*end as usize], 206)
+    } else {
+        headers.set("content-length", &original_length.to_string())?;
+        (&decompressed[..], 200)
+    };
+
+    Ok(Response::from_bytes(slice.to_vec())?
+        .with_headers(headers)
+        .with_status(status))
+}
+
+/// Streams an object's bytes straight from R2, honoring Range natively. Used both for
+/// uncompressed objects and for compressed ones the client can decode itself.
+async fn stream_passthrough(
+    bucket: &Bucket,
+    key: &str,
+    req: &Request,
+    total: u64,
+    algorithm: CompressionAlgorithm,
+) -> Result<Response> {
+    let range = req
+        .headers()
+        .get("range")?
+        .map(|header| parse_range(&header, total))
+        .unwrap_or(RangeRequest::Full);
+
+    if let RangeRequest::Unsatisfiable = range {
+        let mut headers = Headers::new();
+        headers.set("content-range", &format!("bytes */{total}"))?;
+        headers.set("accept-ranges", "bytes")?;
+        return Ok(Response::error("Range Not Satisfiable", 416)?.with_headers(headers));
+    }
+
+    let mut get = bucket.get(key);
+    if let RangeRequest::Satisfiable(ByteRange { start, end }) = &range {
+        get = get.range(Range::OffsetWithLength {
+            offset: *start,
+            length: end - start + 1,
+        });
+    }
+
+    let object = match get.execute().await? {
+        Some(object) => object,
+        None => return Response::error("Not Found", 404),
+    };
+
+    let body = match object.body() {
+        Some(body) => body.stream()?,
+        None => return Response::error("Not Found", 404),
+    };
+
+    let mut headers = Headers::new();
+    headers.set("etag", &object.etag())?;
+    headers.set("accept-ranges", "bytes")?;
+    if let Some(content_type) = object.http_metadata().content_type {
+        headers.set("content-type", &content_type)?;
+    }
+    if let Some(content_encoding) = algorithm.content_encoding() {
+        headers.set("content-encoding", content_encoding)?;
+    }
+
+    let status = if let RangeRequest::Satisfiable(ByteRange { start, end }) = &range {
+        headers.set("content-length", &(end - start + 1).to_string())?;
+        headers.set("content-range", &format!("bytes {start}-{end}/{total}"))?;
+        206
+    } else {
+        headers.set("content-length", &total.to_string())?;
+        200
+    };
+
+    Ok(Response::from_stream(body)?
+        .with_headers(headers)
+        .with_status(status))
+}
+
+pub async fn put_object(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    let result = put_object_inner(req, ctx).await;
+    crate::metrics::record_r2_outcome("put", result.is_ok());
+    result
+}
+
+async fn put_object_inner(mut req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    let bucket = ctx.bucket("BUCKET")?;
+    let key = key_param(&ctx)?;
+
+    if let Some(denied) = check_signature(&req, &ctx, "PUT").await? {
+        return Ok(denied);
+    }
+
+    let url = req.url()?;
+    let compress_param = url
+        .query_pairs()
+        .find(|(k, _)| k == "compress")
+        .map(|(_, v)| v.into_owned());
+    let algorithm = CompressionAlgorithm::from_query_param(compress_param.as_deref(), &ctx);
+
+    // Known limitation: this buffers the whole request body before compressing, rather
+    // than compressing as bytes arrive off `req`'s stream. `flate2`/`zstd`'s encoders
+    // need a synchronous `Read`/`Write`, and bridging that against the request's async
+    // `ReadableStream` isn't wired up here, so memory use is O(upload size) for now.
+    let data = req.bytes().await?;
+    let original_length = data.len() as u64;
+    let body = algorithm.compress(&data)?;
+
+    let mut put = bucket.put(&key, body);
+    if let Some(encoding) = algorithm.metadata_value() {
+        let mut custom_metadata = std::collections::HashMap::new();
+        custom_metadata.insert(METADATA_ALGORITHM.to_string(), encoding.to_string());
+        custom_metadata.insert(METADATA_ORIGINAL_LENGTH.to_string(), original_length.to_string());
+        put = put.custom_metadata(custom_metadata);
+    }
+
+    let object = put.execute().await?;
+
+    Response::from_json(&ObjectSummary {
+        key: object.key(),
+        size: original_length,
+        etag: object.etag(),
+        uploaded: object.uploaded().to_string(),
+    })
+}
+
+pub async fn delete_object(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    let result = delete_object_inner(&req, &ctx).await;
+    crate::metrics::record_r2_outcome("delete", result.is_ok());
+    result
+}
+
+async fn delete_object_inner(req: &Request, ctx: &RouteContext<()>) -> Result<Response> {
+    let bucket = ctx.bucket("BUCKET")?;
+    let key = key_param(ctx)?;
+
+    if let Some(denied) = check_signature(req, ctx, "DELETE").await? {
+        return Ok(denied);
+    }
+
+    bucket.delete(&key).await?;
+
+    Response::empty().map(|r| r.with_status(204))
+}
+
+pub async fn list_objects(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    let bucket = ctx.bucket("BUCKET")?;
+    let url = req.url()?;
+    let query: std::collections::HashMap<_, _> = url.query_pairs().into_owned().collect();
+
+    let mut list = bucket.list();
+    if let Some(prefix) = query.get("prefix") {
+        list = list.prefix(prefix.clone());
+    }
+    if let Some(cursor) = query.get("cursor") {
+        list = list.cursor(cursor.clone());
+    }
+
+    let listing = list.execute().await?;
+
+    let objects = listing
+        .objects()
+        .into_iter()
+        .map(|object| ObjectSummary {
+            key: object.key(),
+            size: object.size(),
+            etag: object.etag(),
+            uploaded: object.uploaded().to_string(),
+        })
+        .collect();
+
+    Response::from_json(&ListPage {
+        objects,
+        truncated: listing.truncated(),
+        cursor: listing.cursor(),
+    })
+}
+
+pub async fn create_multipart_upload(_req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    let bucket = ctx.bucket("BUCKET")?;
+    let key = key_param(&ctx)?;
+
+    let upload = bucket.create_multipart_upload(&key).execute().await?;
+
+    Response::from_json(&serde_json::json!({
+        "key": key,
+        "uploadId": upload.upload_id(),
+    }))
+}
+
+pub async fn upload_part(mut req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    let bucket = ctx.bucket("BUCKET")?;
+    let key = key_param(&ctx)?;
+    let upload_id = ctx
+        .param("uploadId")
+        .ok_or_else(|| Error::RustError("missing uploadId param".into()))?
+        .to_string();
+    let part_number: u16 = ctx
+        .param("partNumber")
+        .and_then(|p| p.parse().ok())
+        .ok_or_else(|| Error::RustError("invalid partNumber param".into()))?;
+
+    let upload = bucket.resume_multipart_upload(&key, &upload_id)?;
+    let data = req.bytes().await?;
+    let part = upload.upload_part(part_number, data).await?;
+
+    Response::from_json(&serde_json::json!({
+        "partNumber": part_number,
+        "etag": part.etag(),
+    }))
+}
+
+pub async fn complete_multipart_upload(mut req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    let bucket = ctx.bucket("BUCKET")?;
+    let key = key_param(&ctx)?;
+    let upload_id = ctx
+        .param("uploadId")
+        .ok_or_else(|| Error::RustError("missing uploadId param".into()))?
+        .to_string();
+
+    let parts: Vec<CompletedPart> = req.json().await?;
+    let uploaded_parts = parts
+        .into_iter()
+        .map(|p| UploadedPart::new(p.part_number, p.etag))
+        .collect();
+
+    let upload = bucket.resume_multipart_upload(&key, &upload_id)?;
+    let object = upload.complete(uploaded_parts).await?;
+
+    Response::from_json(&ObjectSummary {
+        key: object.key(),
+        size: object.size(),
+        etag: object.etag(),
+        uploaded: object.uploaded().to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_range_handles_a_bounded_range() {
+        assert_eq!(
+            parse_range("bytes=0-499", 1000),
+            RangeRequest::Satisfiable(ByteRange { start: 0, end: 499 })
+        );
+    }
+
+    #[test]
+    fn parse_range_handles_an_open_ended_range() {
+        assert_eq!(
+            parse_range("bytes=500-", 1000),
+            RangeRequest::Satisfiable(ByteRange { start: 500, end: 999 })
+        );
+    }
+
+    #[test]
+    fn parse_range_handles_a_suffix_range() {
+        assert_eq!(
+            parse_range("bytes=-500", 1000),
+            RangeRequest::Satisfiable(ByteRange { start: 500, end: 999 })
+        );
+    }
+
+    #[test]
+    fn parse_range_clamps_a_suffix_larger_than_the_object() {
+        assert_eq!(
+            parse_range("bytes=-5000", 1000),
+            RangeRequest::Satisfiable(ByteRange { start: 0, end: 999 })
+        );
+    }
+
+    #[test]
+    fn parse_range_clamps_an_end_past_the_total() {
+        assert_eq!(
+            parse_range("bytes=0-999999", 1000),
+            RangeRequest::Satisfiable(ByteRange { start: 0, end: 999 })
+        );
+    }
+
+    #[test]
+    fn parse_range_is_satisfiable_on_the_last_byte() {
+        assert_eq!(
+            parse_range("bytes=999-999", 1000),
+            RangeRequest::Satisfiable(ByteRange { start: 999, end: 999 })
+        );
+    }
+
+    #[test]
+    fn parse_range_is_unsatisfiable_when_start_is_at_or_past_total() {
+        assert_eq!(parse_range("bytes=1000-1001", 1000), RangeRequest::Unsatisfiable);
+    }
+
+    #[test]
+    fn parse_range_is_unsatisfiable_when_start_is_after_end() {
+        assert_eq!(parse_range("bytes=500-100", 1000), RangeRequest::Unsatisfiable);
+    }
+
+    #[test]
+    fn parse_range_falls_back_to_full_for_multi_range_headers() {
+        assert_eq!(parse_range("bytes=0-10,20-30", 1000), RangeRequest::Full);
+    }
+
+    #[test]
+    fn parse_range_falls_back_to_full_for_malformed_headers() {
+        assert_eq!(parse_range("not-bytes=0-10", 1000), RangeRequest::Full);
+        assert_eq!(parse_range("bytes=abc-def", 1000), RangeRequest::Full);
+    }
+
+    #[test]
+    fn parse_range_falls_back_to_full_for_an_empty_object() {
+        assert_eq!(parse_range("bytes=0-10", 0), RangeRequest::Full);
+    }
+}
@@ -0,0 +1,198 @@
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::Sha256;
+use worker::*;
+
+type HmacSha256 = Hmac<Sha256>;
+
+const DEFAULT_EXPIRES_IN_SECS: u64 = 3600;
+
+#[derive(Deserialize)]
+struct SignRequest {
+    key: String,
+    method: String,
+    #[serde(rename = "expiresIn")]
+    expires_in: Option<u64>,
+}
+
+fn sign(secret: &str, key: &str, method: &str, expires_at: u64) -> Result<String> {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes())
+        .map_err(|e| Error::RustError(format!("invalid signing secret: {e}")))?;
+    mac.update(format!("{key}|{method}|{expires_at}").as_bytes());
+    Ok(hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Result of checking a request's `exp`/`sig` query params against a stored secret.
+#[derive(Debug, PartialEq, Eq)]
+pub enum SignatureCheck {
+    /// No `sig` param was present — the caller should fall back to its normal auth.
+    NotPresent,
+    Valid,
+    Invalid,
+    Expired,
+}
+
+/// Verifies the `exp`/`sig` query params on `req` against an HMAC-SHA256 signature
+/// of `key|method|exp`, using a constant-time comparison to resist timing attacks.
+pub fn verify(req: &Request, key: &str, method: &str, secret: &str, now_secs: u64) -> Result<SignatureCheck> {
+    let url = req.url()?;
+    let query: std::collections::HashMap<_, _> = url.query_pairs().into_owned().collect();
+
+    verify_signature(
+        query.get("exp").map(String::as_str),
+        query.get("sig").map(String::as_str),
+        key,
+        method,
+        secret,
+        now_secs,
+    )
+}
+
+/// The pure signature-checking logic behind `verify`, split out so it's testable without
+/// a `worker::Request`.
+fn verify_signature(
+    exp: Option<&str>,
+    sig: Option<&str>,
+    key: &str,
+    method: &str,
+    secret: &str,
+    now_secs: u64,
+) -> Result<SignatureCheck> {
+    let Some(sig) = sig else {
+        return Ok(SignatureCheck::NotPresent);
+    };
+
+    let Some(Ok(expires_at)) = exp.map(str::parse::<u64>) else {
+        return Ok(SignatureCheck::Invalid);
+    };
+
+    let expected = sign(secret, key, method, expires_at)?;
+    if !constant_time_eq(expected.as_bytes(), sig.as_bytes()) {
+        return Ok(SignatureCheck::Invalid);
+    }
+
+    if now_secs >= expires_at {
+        return Ok(SignatureCheck::Expired);
+    }
+
+    Ok(SignatureCheck::Valid)
+}
+
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+pub async fn sign_handler(mut req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    let body: SignRequest = req.json().await?;
+    let secret = ctx.secret("SIGNING_SECRET")?.to_string();
+    let now_secs = Date::now().as_millis() / 1000;
+    let expires_at = now_secs + body.expires_in.unwrap_or(DEFAULT_EXPIRES_IN_SECS);
+
+    let sig = sign(&secret, &body.key, &body.method, expires_at)?;
+    let url = format!("/objects/{}?exp={}&sig={}", body.key, expires_at, sig);
+
+    Response::from_json(&serde_json::json!({
+        "url": url,
+        "expires": expires_at,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &str = "test-secret";
+
+    #[test]
+    fn sign_is_deterministic_for_the_same_inputs() {
+        let a = sign(SECRET, "report.csv", "GET", 1_700_000_000).unwrap();
+        let b = sign(SECRET, "report.csv", "GET", 1_700_000_000).unwrap();
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn sign_differs_when_any_signed_field_changes() {
+        let base = sign(SECRET, "report.csv", "GET", 1_700_000_000).unwrap();
+        assert_ne!(base, sign(SECRET, "other.csv", "GET", 1_700_000_000).unwrap());
+        assert_ne!(base, sign(SECRET, "report.csv", "PUT", 1_700_000_000).unwrap());
+        assert_ne!(base, sign(SECRET, "report.csv", "GET", 1_700_000_001).unwrap());
+    }
+
+    #[test]
+    fn verify_accepts_a_valid_unexpired_signature() {
+        let expires_at = 1_700_000_100;
+        let sig = sign(SECRET, "report.csv", "GET", expires_at).unwrap();
+        let result = verify_signature(
+            Some(&expires_at.to_string()),
+            Some(&sig),
+            "report.csv",
+            "GET",
+            SECRET,
+            1_700_000_000,
+        )
+        .unwrap();
+        assert_eq!(result, SignatureCheck::Valid);
+    }
+
+    #[test]
+    fn verify_reports_not_present_with_no_query_params() {
+        let result = verify_signature(None, None, "report.csv", "GET", SECRET, 1_700_000_000).unwrap();
+        assert_eq!(result, SignatureCheck::NotPresent);
+    }
+
+    #[test]
+    fn verify_rejects_a_tampered_signature() {
+        let expires_at = 1_700_000_100;
+        let sig = sign(SECRET, "report.csv", "GET", expires_at).unwrap();
+        let result = verify_signature(
+            Some(&expires_at.to_string()),
+            Some(&sig),
+            "other.csv",
+            "GET",
+            SECRET,
+            1_700_000_000,
+        )
+        .unwrap();
+        assert_eq!(result, SignatureCheck::Invalid);
+    }
+
+    #[test]
+    fn verify_rejects_an_unparseable_expiry() {
+        let result = verify_signature(
+            Some("not-a-number"),
+            Some("deadbeef"),
+            "report.csv",
+            "GET",
+            SECRET,
+            1_700_000_000,
+        )
+        .unwrap();
+        assert_eq!(result, SignatureCheck::Invalid);
+    }
+
+    #[test]
+    fn verify_reports_expired_once_past_the_deadline() {
+        let expires_at = 1_700_000_000;
+        let sig = sign(SECRET, "report.csv", "GET", expires_at).unwrap();
+        let result = verify_signature(
+            Some(&expires_at.to_string()),
+            Some(&sig),
+            "report.csv",
+            "GET",
+            SECRET,
+            expires_at,
+        )
+        .unwrap();
+        assert_eq!(result, SignatureCheck::Expired);
+    }
+
+    #[test]
+    fn constant_time_eq_requires_matching_length_and_bytes() {
+        assert!(constant_time_eq(b"abc", b"abc"));
+        assert!(!constant_time_eq(b"abc", b"abd"));
+        assert!(!constant_time_eq(b"abc", b"ab"));
+    }
+}
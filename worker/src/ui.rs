@@ -0,0 +1,54 @@
+use rust_embed::RustEmbed;
+use worker::*;
+
+/// The built file-explorer SPA, baked into the WASM binary at compile time.
+#[derive(RustEmbed)]
+#[folder = "static/"]
+struct Assets;
+
+fn content_type_for(path: &str) -> &'static str {
+    match path.rsplit('.').next() {
+        Some("html") => "text/html; charset=utf-8",
+        Some("js") => "text/javascript; charset=utf-8",
+        Some("css") => "text/css; charset=utf-8",
+        Some("json") => "application/json",
+        Some("svg") => "image/svg+xml",
+        Some("png") => "image/png",
+        Some("ico") => "image/x-icon",
+        Some("woff2") => "font/woff2",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Looks up `path`, falling back to `index.html` so client-side routes resolve.
+/// Returns the path the asset actually came from, since that (not the requested
+/// path) determines the right `Content-Type` for a fallback hit.
+fn lookup(path: &str) -> Option<(&str, rust_embed::EmbeddedFile)> {
+    Assets::get(path)
+        .map(|asset| (path, asset))
+        .or_else(|| Assets::get("index.html").map(|asset| ("index.html", asset)))
+}
+
+pub async fn serve_ui(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    let path = ctx.param("path").unwrap_or("index.html").trim_start_matches('/');
+    let path = if path.is_empty() { "index.html" } else { path };
+
+    let (served_path, asset) = match lookup(path) {
+        Some(found) => found,
+        None => return Response::error("Not Found", 404),
+    };
+
+    let etag = format!("\"{}\"", hex::encode(asset.metadata.sha256_hash()));
+    if let Some(if_none_match) = req.headers().get("if-none-match")? {
+        if if_none_match == etag {
+            return Ok(Response::empty()?.with_status(304));
+        }
+    }
+
+    let mut headers = Headers::new();
+    headers.set("content-type", content_type_for(served_path))?;
+    headers.set("etag", &etag)?;
+    headers.set("cache-control", "public, max-age=3600")?;
+
+    Ok(Response::from_bytes(asset.data.to_vec())?.with_headers(headers))
+}